@@ -1,10 +1,20 @@
-use std::ops::Range;
+// Each build strategy/traversal mode lives here as a standalone API
+// (`cast_ray`, `query_overlaps`, `query_aabb`, `into_wide4`, `BuildStrategy`
+// variants, ...) even though `main`'s demo only wires up one path at a time;
+// that leaves plenty of real, reachable code dead from the compiler's point
+// of view.
+#![allow(dead_code)]
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use itertools::Itertools;
 use nannou::{glam::Vec3Swizzles, prelude::*};
 use rand::Rng;
+use rayon::join;
 
 #[derive(Default, Clone, Copy, Debug)]
+#[allow(clippy::upper_case_acronyms)]
 struct AABB {
     lb: Vec3,
     ub: Vec3,
@@ -26,6 +36,97 @@ impl AABB {
             ub: self.ub.max(other.ub),
         }
     }
+
+    // Identity element for `union`: anything unioned with this yields itself.
+    fn empty() -> AABB {
+        AABB {
+            lb: Vec3::splat(f32::INFINITY),
+            ub: Vec3::splat(f32::NEG_INFINITY),
+        }
+    }
+
+    fn surface_area(&self) -> f32 {
+        let d = (self.ub - self.lb).max(Vec3::ZERO);
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    // Bounds of the region the two boxes have in common. Not a box at all
+    // when they don't overlap (`lb` past `ub` on some axis); `surface_area`
+    // clamps that negative extent to zero but still counts the face spanned
+    // by the other two axes, so check `overlaps` before scoring it.
+    fn intersection(&self, other: &AABB) -> AABB {
+        AABB {
+            lb: self.lb.max(other.lb),
+            ub: self.ub.min(other.ub),
+        }
+    }
+
+    // Clamps the box to lie within `[lo, hi]` on `axis`, leaving the other
+    // two axes untouched. Used to clip a primitive's AABB into a spatial bin
+    // when scoring spatial splits; a child's bounds are built from the
+    // unclipped AABBs of its references.
+    fn clipped_to_axis_range(&self, axis: usize, lo: f32, hi: f32) -> AABB {
+        let mut lb = self.lb;
+        let mut ub = self.ub;
+        match axis {
+            0 => {
+                lb.x = lb.x.max(lo);
+                ub.x = ub.x.min(hi);
+            }
+            1 => {
+                lb.y = lb.y.max(lo);
+                ub.y = ub.y.min(hi);
+            }
+            _ => {
+                lb.z = lb.z.max(lo);
+                ub.z = ub.z.min(hi);
+            }
+        }
+        AABB { lb, ub }
+    }
+
+    fn overlaps(&self, other: &AABB) -> bool {
+        self.lb.x <= other.ub.x
+            && self.ub.x >= other.lb.x
+            && self.lb.y <= other.ub.y
+            && self.ub.y >= other.lb.y
+            && self.lb.z <= other.ub.z
+            && self.ub.z >= other.lb.z
+    }
+
+    // Slab method: shrink [t_min, t_max] against each axis' pair of planes,
+    // bailing out as soon as the interval is empty.
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> bool {
+        let mut t_min = t_min;
+        let mut t_max = t_max;
+        for a in 0..3 {
+            let inv_d = 1.0 / ray.dir[a];
+            let mut t0 = (self.lb[a] - ray.origin[a]) * inv_d;
+            let mut t1 = (self.ub[a] - ray.origin[a]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            if t_max <= t_min {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Ray {
+    origin: Vec3,
+    dir: Vec3,
+}
+
+// Anything a BVH can index: it just needs to report its own bounds and a
+// representative point to bin/partition on.
+trait Bounded {
+    fn aabb(&self) -> AABB;
+    fn centroid(&self) -> Vec3;
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -34,7 +135,7 @@ struct Circle {
     radius: f32,
 }
 
-impl Circle {
+impl Bounded for Circle {
     fn aabb(&self) -> AABB {
         AABB {
             lb: self.translation - self.radius,
@@ -42,6 +143,35 @@ impl Circle {
         }
     }
 
+    fn centroid(&self) -> Vec3 {
+        self.translation
+    }
+}
+
+impl Circle {
+    // Standard ray-sphere quadratic; returns the nearest root within [t_min, t_max].
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<f32> {
+        let oc = ray.origin - self.translation;
+        let a = ray.dir.length_squared();
+        let b = oc.dot(ray.dir);
+        let c = oc.length_squared() - self.radius * self.radius;
+
+        let discriminant = b * b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let sqrt_d = discriminant.sqrt();
+
+        let mut t = (-b - sqrt_d) / a;
+        if t < t_min || t > t_max {
+            t = (-b + sqrt_d) / a;
+            if t < t_min || t > t_max {
+                return None;
+            }
+        }
+        Some(t)
+    }
+
     fn draw(&self, draw: &Draw) {
         draw.ellipse()
             .xy(self.translation.xy())
@@ -50,7 +180,7 @@ impl Circle {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 enum BVHNode {
     Internal {
         // Bounds of this BVH:
@@ -62,9 +192,10 @@ enum BVHNode {
     Leaf {
         // Bounds of this BVH:
         bounds: AABB,
-        // Contained primitives
-        start: usize,
-        end: usize, // non inclusive
+        // Indices into the shared object array. An indirection rather than a
+        // contiguous `start..end` range, since spatial splits (`BuildStrategy::Sbvh`)
+        // duplicate a straddling primitive's index into both children.
+        refs: Vec<usize>,
     },
 }
 
@@ -82,148 +213,886 @@ impl BVHNode {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BuildStrategy {
+    /// Split on the spatial midpoint of the node's longest axis.
+    Median,
+    /// Split using a binned Surface Area Heuristic.
+    Sah,
+    /// Binned SAH object splits, plus a binned spatial split considered
+    /// alongside it (SBVH-style reference splitting).
+    Sbvh,
+}
+
+// Number of bins a node's extent is divided into when evaluating SAH split
+// planes, for both object splits (centroid bins) and spatial splits (space
+// bins).
+const SAH_BINS: usize = 16;
+
+#[derive(Clone, Copy)]
+struct Bin {
+    count: usize,
+    bounds: AABB,
+}
+
+impl Default for Bin {
+    fn default() -> Self {
+        Bin {
+            count: 0,
+            bounds: AABB::empty(),
+        }
+    }
+}
+
+// A spatial bin additionally tracks how many primitive references *enter*
+// and *exit* its span, so a straddling reference (duplicated into every bin
+// it touches) is still only counted once per side of a candidate plane.
+#[derive(Clone, Copy)]
+struct SpatialBin {
+    bounds: AABB,
+    entries: usize,
+    exits: usize,
+}
+
+impl Default for SpatialBin {
+    fn default() -> Self {
+        SpatialBin {
+            bounds: AABB::empty(),
+            entries: 0,
+            exits: 0,
+        }
+    }
+}
+
+// Default leaf size: a node stops subdividing once it holds this many
+// objects or fewer.
+const LEAF_THRESHOLD: usize = 2;
+
+// Below this many references in a node, its children are built sequentially
+// rather than handed to `rayon::join` — not worth the spawn overhead.
+const DEFAULT_PARALLEL_THRESHOLD: usize = 50_000;
+
+// A spatial split is only worth its reference duplication when the object
+// split it's replacing leaves its children overlapping by more than this
+// fraction of the surface area of the node being split.
+const SBVH_OVERLAP_THRESHOLD: f32 = 1e-5;
+
+// Hard recursion-depth cap on spatial splits: past this depth `build_subtree`
+// only considers object splits, which are guaranteed to shrink both children.
+// Backstops `SBVH_DUPLICATE_BUDGET_FACTOR` in case some future split heuristic
+// produces a near-degenerate (but not caught by the strict-subset check)
+// spatial split that barely shrinks one side.
+const SBVH_MAX_DEPTH: usize = 64;
+
+// Total extra reference duplication spatial splits may introduce over the
+// course of one build, expressed as a multiple of the object count. Once
+// exhausted, spatial splits fall back to object splits, bounding total
+// duplication (and thus memory) to a linear multiple of the input size.
+const SBVH_DUPLICATE_BUDGET_FACTOR: f32 = 1.0;
+
+// Atomically reserves `amount` from the SBVH duplication budget, returning
+// `false` (and reserving nothing) if fewer than `amount` references remain
+// in the budget.
+fn try_reserve_dup_budget(budget: &AtomicUsize, amount: usize) -> bool {
+    budget
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |b| {
+            b.checked_sub(amount)
+        })
+        .is_ok()
+}
+
+// Union of the AABBs of the objects named by `refs`. Panics on an empty
+// slice; `build_subtree` returns an empty leaf for empty `refs` before
+// calling it.
+fn bounds_of_refs<O: Bounded>(objects: &[O], refs: &[usize]) -> AABB {
+    let mut bounds = objects[refs[0]].aabb();
+    for &i in &refs[1..] {
+        bounds = bounds.union(&objects[i].aabb());
+    }
+    bounds
+}
+
+fn leaf_node(bounds: AABB, refs: Vec<usize>) -> (Vec<BVHNode>, usize) {
+    (vec![BVHNode::Leaf { bounds, refs }], 0)
+}
+
+// The plane a node should be divided on, and whether dividing on it means
+// partitioning references (object split) or duplicating straddling ones
+// (spatial split).
+enum SplitChoice {
+    Object { axis: usize, value: f32 },
+    Spatial { axis: usize, value: f32 },
+}
+
+// Picks the split axis+plane for a node according to `strategy`.
+fn choose_split<O: Bounded>(
+    objects: &[O],
+    refs: &[usize],
+    bounds: &AABB,
+    strategy: BuildStrategy,
+) -> Option<SplitChoice> {
+    match strategy {
+        BuildStrategy::Median => {
+            // Compute the longest axis, on which we will split
+            let extent = bounds.ub - bounds.lb;
+            let mut axis = 0;
+            if extent.y > extent.x {
+                axis = 1
+            };
+            if extent.z > extent[axis] {
+                axis = 2
+            };
+
+            Some(SplitChoice::Object {
+                axis,
+                value: bounds.lb[axis] + extent[axis] / 2.0,
+            })
+        }
+        BuildStrategy::Sah => {
+            let leaf_cost = bounds.surface_area() * refs.len() as f32;
+            choose_object_split(objects, refs, bounds)
+                .filter(|(_, _, cost, ..)| *cost < leaf_cost)
+                .map(|(axis, value, ..)| SplitChoice::Object { axis, value })
+        }
+        BuildStrategy::Sbvh => choose_split_sbvh(objects, refs, bounds),
+    }
+}
+
+// Binned SAH object split: project centroids onto each axis into SAH_BINS
+// bins, sweep prefix/suffix bounds and counts, and score every plane between
+// adjacent bins as surfaceArea(left)*leftCount + surfaceArea(right)*rightCount.
+// Returns the winning plane's cost and child bounds even when it doesn't
+// beat the leaf cost, so callers (`Sbvh`) can compare it against a spatial
+// split before deciding whether to keep the node a leaf at all.
+fn choose_object_split<O: Bounded>(
+    objects: &[O],
+    refs: &[usize],
+    _bounds: &AABB,
+) -> Option<(usize, f32, f32, AABB, AABB)> {
+    let mut best: Option<(usize, f32, f32, AABB, AABB)> = None; // (axis, split value, cost, left bounds, right bounds)
+
+    for axis in 0..3 {
+        let mut c_min = f32::INFINITY;
+        let mut c_max = f32::NEG_INFINITY;
+        for &i in refs {
+            let c = objects[i].centroid()[axis];
+            c_min = c_min.min(c);
+            c_max = c_max.max(c);
+        }
+        let extent = c_max - c_min;
+        if extent <= f32::EPSILON {
+            continue;
+        }
+
+        let mut bins = [Bin::default(); SAH_BINS];
+        for &i in refs {
+            let bin = (((objects[i].centroid()[axis] - c_min) / extent * SAH_BINS as f32) as usize)
+                .min(SAH_BINS - 1);
+            bins[bin].count += 1;
+            bins[bin].bounds = bins[bin].bounds.union(&objects[i].aabb());
+        }
+
+        let mut left_count = [0usize; SAH_BINS];
+        let mut left_bounds = [AABB::empty(); SAH_BINS];
+        let (mut acc_count, mut acc_bounds) = (0, AABB::empty());
+        for i in 0..SAH_BINS {
+            acc_count += bins[i].count;
+            acc_bounds = acc_bounds.union(&bins[i].bounds);
+            left_count[i] = acc_count;
+            left_bounds[i] = acc_bounds;
+        }
+
+        let mut right_count = [0usize; SAH_BINS];
+        let mut right_bounds = [AABB::empty(); SAH_BINS];
+        let (mut acc_count, mut acc_bounds) = (0, AABB::empty());
+        for i in (0..SAH_BINS).rev() {
+            acc_count += bins[i].count;
+            acc_bounds = acc_bounds.union(&bins[i].bounds);
+            right_count[i] = acc_count;
+            right_bounds[i] = acc_bounds;
+        }
+
+        for i in 0..SAH_BINS - 1 {
+            let (l_count, r_count) = (left_count[i], right_count[i + 1]);
+            if l_count == 0 || r_count == 0 {
+                continue;
+            }
+            let cost = left_bounds[i].surface_area() * l_count as f32
+                + right_bounds[i + 1].surface_area() * r_count as f32;
+            if best.is_none_or(|(_, _, best_cost, ..)| cost < best_cost) {
+                let split_value = c_min + extent * (i + 1) as f32 / SAH_BINS as f32;
+                best = Some((axis, split_value, cost, left_bounds[i], right_bounds[i + 1]));
+            }
+        }
+    }
+
+    best
+}
+
+// Binned spatial split: bin the node's *spatial* extent (not centroids) on
+// each axis, and for every reference whose AABB straddles a bin boundary,
+// clip a copy of it into each bin it touches (reference splitting) so the
+// bin's bounds don't include space the reference doesn't actually occupy in
+// that bin. Entry/exit counts per bin let the plane sweep count a
+// duplicated reference exactly once on each side, rather than twice.
+fn choose_spatial_split<O: Bounded>(
+    objects: &[O],
+    refs: &[usize],
+    bounds: &AABB,
+) -> Option<(usize, f32, f32, AABB, AABB)> {
+    let mut best: Option<(usize, f32, f32, AABB, AABB)> = None;
+
+    for axis in 0..3 {
+        let c_min = bounds.lb[axis];
+        let c_max = bounds.ub[axis];
+        let extent = c_max - c_min;
+        if extent <= f32::EPSILON {
+            continue;
+        }
+        let bin_width = extent / SAH_BINS as f32;
+        let bin_of = |v: f32| (((v - c_min) / extent * SAH_BINS as f32) as usize).min(SAH_BINS - 1);
+
+        let mut bins = [SpatialBin::default(); SAH_BINS];
+        for &i in refs {
+            let aabb = objects[i].aabb();
+            let first = bin_of(aabb.lb[axis]);
+            let last = bin_of(aabb.ub[axis]);
+            bins[first].entries += 1;
+            bins[last].exits += 1;
+            for (b, bin) in bins.iter_mut().enumerate().take(last + 1).skip(first) {
+                let bin_lo = c_min + b as f32 * bin_width;
+                let bin_hi = bin_lo + bin_width;
+                bin.bounds = bin
+                    .bounds
+                    .union(&aabb.clipped_to_axis_range(axis, bin_lo, bin_hi));
+            }
+        }
+
+        let mut left_count = [0usize; SAH_BINS];
+        let mut left_bounds = [AABB::empty(); SAH_BINS];
+        let (mut acc_count, mut acc_bounds) = (0, AABB::empty());
+        for i in 0..SAH_BINS {
+            acc_count += bins[i].entries;
+            acc_bounds = acc_bounds.union(&bins[i].bounds);
+            left_count[i] = acc_count;
+            left_bounds[i] = acc_bounds;
+        }
+
+        let mut right_count = [0usize; SAH_BINS];
+        let mut right_bounds = [AABB::empty(); SAH_BINS];
+        let (mut acc_count, mut acc_bounds) = (0, AABB::empty());
+        for i in (0..SAH_BINS).rev() {
+            acc_count += bins[i].exits;
+            acc_bounds = acc_bounds.union(&bins[i].bounds);
+            right_count[i] = acc_count;
+            right_bounds[i] = acc_bounds;
+        }
+
+        for i in 0..SAH_BINS - 1 {
+            let (l_count, r_count) = (left_count[i], right_count[i + 1]);
+            if l_count == 0 || r_count == 0 {
+                continue;
+            }
+            let cost = left_bounds[i].surface_area() * l_count as f32
+                + right_bounds[i + 1].surface_area() * r_count as f32;
+            if best.is_none_or(|(_, _, best_cost, ..)| cost < best_cost) {
+                let split_value = c_min + bin_width * (i + 1) as f32;
+                best = Some((axis, split_value, cost, left_bounds[i], right_bounds[i + 1]));
+            }
+        }
+    }
+
+    best
+}
+
+// SBVH node split: evaluate the usual binned SAH object split, plus a
+// binned spatial split, and prefer the spatial one only when it scores
+// better *and* the object split's children would overlap by more than
+// `SBVH_OVERLAP_THRESHOLD` of the parent's surface area — duplicating
+// references is only worth it when an object split alone leaves the
+// children badly overlapping.
+fn choose_split_sbvh<O: Bounded>(objects: &[O], refs: &[usize], bounds: &AABB) -> Option<SplitChoice> {
+    let leaf_cost = bounds.surface_area() * refs.len() as f32;
+    let root_area = bounds.surface_area();
+
+    let object = choose_object_split(objects, refs, bounds);
+    let spatial = choose_spatial_split(objects, refs, bounds);
+
+    let prefer_spatial = match (&object, &spatial) {
+        (Some((_, _, obj_cost, obj_left, obj_right)), Some((_, _, spat_cost, ..))) => {
+            let overlap = if obj_left.overlaps(obj_right) {
+                obj_left.intersection(obj_right).surface_area()
+            } else {
+                0.0
+            };
+            spat_cost < obj_cost && overlap > SBVH_OVERLAP_THRESHOLD * root_area
+        }
+        (None, Some(_)) => true,
+        _ => false,
+    };
+
+    let (axis, value, cost) = if prefer_spatial {
+        let (axis, value, cost, ..) = spatial?;
+        (axis, value, cost)
+    } else {
+        let (axis, value, cost, ..) = object?;
+        (axis, value, cost)
+    };
+
+    if cost >= leaf_cost {
+        return None;
+    }
+
+    Some(if prefer_spatial {
+        SplitChoice::Spatial { axis, value }
+    } else {
+        SplitChoice::Object { axis, value }
+    })
+}
+
+// Partitions `refs` by centroid around `split` on `axis`. Every reference
+// ends up on exactly one side.
+fn partition_refs<O: Bounded>(objects: &[O], refs: Vec<usize>, axis: usize, split: f32) -> (Vec<usize>, Vec<usize>) {
+    let mut left = Vec::new();
+    let mut right = Vec::new();
+    for i in refs {
+        if objects[i].centroid()[axis] < split {
+            left.push(i);
+        } else {
+            right.push(i);
+        }
+    }
+    (left, right)
+}
+
+// Partitions `refs` by AABB around `split` on `axis`. A reference fully on
+// one side goes there; one whose AABB straddles the plane is duplicated
+// into both sides (reference splitting). Borrows `refs` rather than
+// consuming it so callers can fall back to an object split on the same refs
+// when the spatial split turns out not to be worth taking.
+fn spatial_partition_refs<O: Bounded>(
+    objects: &[O],
+    refs: &[usize],
+    axis: usize,
+    split: f32,
+) -> (Vec<usize>, Vec<usize>) {
+    let mut left = Vec::new();
+    let mut right = Vec::new();
+    for &i in refs {
+        let aabb = objects[i].aabb();
+        if aabb.ub[axis] <= split {
+            left.push(i);
+        } else if aabb.lb[axis] >= split {
+            right.push(i);
+        } else {
+            left.push(i);
+            right.push(i);
+        }
+    }
+    (left, right)
+}
+
+// Shifts the node indices referenced by an `Internal` node by `offset`,
+// leaving `Leaf` refs (which index into the shared object array, not
+// `nodes`) untouched.
+fn offset_node(node: BVHNode, offset: usize) -> BVHNode {
+    match node {
+        BVHNode::Internal { bounds, left, right } => BVHNode::Internal {
+            bounds,
+            left: left + offset,
+            right: right + offset,
+        },
+        leaf => leaf,
+    }
+}
+
+// Recursively builds the subtree covering the primitives named by `refs`,
+// picking a split plane and then partitioning (or, for `Sbvh` spatial
+// splits, duplicating) references into two owned reference lists, then
+// building the two children independently — in parallel via `rayon::join`
+// once a node's reference count is at least `parallel_threshold`, sequentially
+// otherwise. Each call returns its own node buffer addressed with local
+// indices plus the index of its root within that buffer; callers stitch
+// subtrees together by appending and offsetting.
+//
+// A spatial split is only ever taken if it shrinks *both* children relative
+// to `refs` (otherwise a straddling reference can make one side an exact
+// copy of the parent, which would recurse forever on identical input), past
+// `SBVH_MAX_DEPTH` they're skipped entirely, and `dup_budget` caps how many
+// extra references spatial splits may create over the whole build. Object
+// splits always shrink both children, so falling back to one always makes
+// progress.
+fn build_subtree<O: Bounded + Sync>(
+    objects: &[O],
+    refs: Vec<usize>,
+    strategy: BuildStrategy,
+    parallel_threshold: usize,
+    depth: usize,
+    dup_budget: &AtomicUsize,
+) -> (Vec<BVHNode>, usize) {
+    // `bounds_of_refs` indexes `refs[0]`, which doesn't exist for a `BVH`
+    // built from an empty `Vec<O>` (plausible now that `O` is generic rather
+    // than always populated with circles).
+    if refs.is_empty() {
+        return leaf_node(AABB::empty(), refs);
+    }
+
+    let bounds = bounds_of_refs(objects, &refs);
+    let len = refs.len();
+
+    if len <= LEAF_THRESHOLD {
+        return leaf_node(bounds, refs);
+    }
+
+    let Some(split) = choose_split(objects, &refs, &bounds, strategy) else {
+        return leaf_node(bounds, refs);
+    };
+
+    let (left_refs, right_refs) = match split {
+        SplitChoice::Object { axis, value } => partition_refs(objects, refs, axis, value),
+        SplitChoice::Spatial { axis, value } => {
+            let spatial = (depth < SBVH_MAX_DEPTH).then(|| spatial_partition_refs(objects, &refs, axis, value));
+            match spatial {
+                Some((l, r))
+                    if l.len() < len
+                        && r.len() < len
+                        && try_reserve_dup_budget(dup_budget, l.len() + r.len() - len) =>
+                {
+                    (l, r)
+                }
+                _ => partition_refs(objects, refs, axis, value),
+            }
+        }
+    };
+
+    if left_refs.is_empty() || right_refs.is_empty() {
+        let refs = left_refs.into_iter().chain(right_refs).collect();
+        return leaf_node(bounds, refs);
+    }
+
+    let (left, right) = if len >= parallel_threshold {
+        join(
+            || build_subtree(objects, left_refs, strategy, parallel_threshold, depth + 1, dup_budget),
+            || build_subtree(objects, right_refs, strategy, parallel_threshold, depth + 1, dup_budget),
+        )
+    } else {
+        (
+            build_subtree(objects, left_refs, strategy, parallel_threshold, depth + 1, dup_budget),
+            build_subtree(objects, right_refs, strategy, parallel_threshold, depth + 1, dup_budget),
+        )
+    };
+
+    let (mut nodes, left_root) = left;
+    let (right_nodes, right_root) = right;
+
+    let offset = nodes.len();
+    nodes.extend(right_nodes.into_iter().map(|n| offset_node(n, offset)));
+
+    nodes.push(BVHNode::Internal {
+        bounds,
+        left: left_root,
+        right: right_root + offset,
+    });
+    let this_root = nodes.len() - 1;
+
+    (nodes, this_root)
+}
+
 #[derive(Debug)]
-struct BVH {
+#[allow(clippy::upper_case_acronyms)]
+struct BVH<O: Bounded> {
     nodes: Vec<BVHNode>,
-    circles: Vec<Circle>,
+    root: usize,
+    objects: Vec<O>,
+    strategy: BuildStrategy,
+}
+
+impl<O: Bounded + Sync> BVH<O> {
+    fn new(objects: Vec<O>, strategy: BuildStrategy) -> BVH<O> {
+        Self::new_with_parallel_threshold(objects, strategy, DEFAULT_PARALLEL_THRESHOLD)
+    }
+
+    fn new_with_parallel_threshold(
+        objects: Vec<O>,
+        strategy: BuildStrategy,
+        parallel_threshold: usize,
+    ) -> BVH<O> {
+        let refs = (0..objects.len()).collect();
+        let dup_budget = AtomicUsize::new((objects.len() as f32 * SBVH_DUPLICATE_BUDGET_FACTOR) as usize);
+        let (nodes, root) = build_subtree(&objects, refs, strategy, parallel_threshold, 0, &dup_budget);
+
+        BVH {
+            nodes,
+            root,
+            objects,
+            strategy,
+        }
+    }
+}
+
+impl<O: Bounded> BVH<O> {
+    // Broad-phase: every pair of primitives whose AABBs overlap, found by
+    // descending the tree against itself and pruning pairs of subtrees whose
+    // bounds don't intersect.
+    //
+    // Spatial splits (`BuildStrategy::Sbvh`) duplicate a straddling
+    // primitive's index into more than one leaf, so the same unordered pair
+    // can otherwise surface more than once; collapse those before returning.
+    // The degenerate pair formed by a duplicated index against itself is
+    // skipped in the leaf-vs-leaf test and never reported.
+    fn query_overlaps(&self) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        self.query_overlaps_nodes(self.root, self.root, &mut pairs);
+        let mut seen = HashSet::with_capacity(pairs.len());
+        pairs.retain(|&(i, j)| seen.insert(if i <= j { (i, j) } else { (j, i) }));
+        pairs
+    }
+
+    fn query_overlaps_nodes(&self, a_idx: usize, b_idx: usize, pairs: &mut Vec<(usize, usize)>) {
+        let a = &self.nodes[a_idx];
+        let b = &self.nodes[b_idx];
+        if !a.bounds().overlaps(&b.bounds()) {
+            return;
+        }
+
+        match (a, b) {
+            (
+                BVHNode::Internal {
+                    left: al,
+                    right: ar,
+                    ..
+                },
+                BVHNode::Internal {
+                    left: bl,
+                    right: br,
+                    ..
+                },
+            ) => {
+                let (al, ar, bl, br) = (*al, *ar, *bl, *br);
+                if a_idx == b_idx {
+                    // Comparing a subtree against itself: only the unordered
+                    // pairs of descendants are needed, each exactly once.
+                    self.query_overlaps_nodes(al, al, pairs);
+                    self.query_overlaps_nodes(al, ar, pairs);
+                    self.query_overlaps_nodes(ar, ar, pairs);
+                } else {
+                    self.query_overlaps_nodes(al, bl, pairs);
+                    self.query_overlaps_nodes(al, br, pairs);
+                    self.query_overlaps_nodes(ar, bl, pairs);
+                    self.query_overlaps_nodes(ar, br, pairs);
+                }
+            }
+            (BVHNode::Internal { left, right, .. }, BVHNode::Leaf { .. }) => {
+                let (left, right) = (*left, *right);
+                self.query_overlaps_nodes(left, b_idx, pairs);
+                self.query_overlaps_nodes(right, b_idx, pairs);
+            }
+            (BVHNode::Leaf { .. }, BVHNode::Internal { left, right, .. }) => {
+                let (left, right) = (*left, *right);
+                self.query_overlaps_nodes(a_idx, left, pairs);
+                self.query_overlaps_nodes(a_idx, right, pairs);
+            }
+            (BVHNode::Leaf { refs: a_refs, .. }, BVHNode::Leaf { refs: b_refs, .. }) => {
+                for (m, &i) in a_refs.iter().enumerate() {
+                    // Comparing a leaf against itself: skip positions already
+                    // visited so each unordered pair is only emitted once.
+                    let skip = if a_idx == b_idx { m + 1 } else { 0 };
+                    for &j in &b_refs[skip..] {
+                        // A duplicated reference (from an Sbvh spatial
+                        // split) can appear in both `a_refs` and `b_refs`
+                        // even when the leaves are distinct; that's not a
+                        // pair, it's the same primitive with itself.
+                        if i == j {
+                            continue;
+                        }
+                        if self.objects[i].aabb().overlaps(&self.objects[j].aabb()) {
+                            pairs.push((i, j));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Collects every primitive whose AABB touches `region`, skipping
+    // subtrees whose bounds miss it entirely.
+    //
+    // Spatial splits (`BuildStrategy::Sbvh`) duplicate a straddling
+    // primitive's index into more than one leaf, so the same index can
+    // otherwise be collected once per containing leaf that overlaps
+    // `region`; dedup for the same reason `query_overlaps` does.
+    fn query_aabb(&self, region: AABB) -> Vec<usize> {
+        let mut out = Vec::new();
+        self.query_aabb_node(self.root, &region, &mut out);
+        let mut seen = HashSet::with_capacity(out.len());
+        out.retain(|&i| seen.insert(i));
+        out
+    }
+
+    fn query_aabb_node(&self, node_idx: usize, region: &AABB, out: &mut Vec<usize>) {
+        let node = &self.nodes[node_idx];
+        if !node.bounds().overlaps(region) {
+            return;
+        }
+
+        match node {
+            BVHNode::Internal { left, right, .. } => {
+                self.query_aabb_node(*left, region, out);
+                self.query_aabb_node(*right, region, out);
+            }
+            BVHNode::Leaf { refs, .. } => {
+                for &i in refs {
+                    if self.objects[i].aabb().overlaps(region) {
+                        out.push(i);
+                    }
+                }
+            }
+        }
+    }
 }
 
-impl BVH {
+// Ray casting and drawing need `Circle::hit`/`Circle::draw`, which aren't
+// part of `Bounded`, so they live on the concrete instantiation rather than
+// the generic impl above.
+impl BVH<Circle> {
     fn draw(&self, draw: &Draw) {
         for node in &self.nodes {
             node.draw(draw);
         }
-        for circle in &self.circles {
+        for circle in &self.objects {
             circle.draw(draw);
         }
     }
 
-    fn compute_bounds(&mut self, node_idx: usize) {
-        let mut node = self.nodes[node_idx];
-        match &mut node {
-            BVHNode::Internal {
-                bounds,
-                left,
-                right,
-            } => {
-                let l = &self.nodes[*left];
-                let r = &self.nodes[*right];
-                *bounds = l.bounds().union(&r.bounds());
+    // Walks the tree from the root, skipping subtrees whose bounds miss the
+    // ray, and returns the closest hit circle along with its `t`.
+    fn cast_ray(&self, ray: &Ray) -> Option<(usize, f32)> {
+        self.cast_ray_node(self.root, ray, 0.0001, f32::INFINITY)
+    }
+
+    fn cast_ray_node(&self, node_idx: usize, ray: &Ray, t_min: f32, t_max: f32) -> Option<(usize, f32)> {
+        let node = &self.nodes[node_idx];
+        if !node.bounds().hit(ray, t_min, t_max) {
+            return None;
+        }
+
+        match node {
+            BVHNode::Internal { left, right, .. } => {
+                let hit_left = self.cast_ray_node(*left, ray, t_min, t_max);
+                let right_t_max = hit_left.map_or(t_max, |(_, t)| t);
+                let hit_right = self.cast_ray_node(*right, ray, t_min, right_t_max);
+                hit_right.or(hit_left)
             }
-            BVHNode::Leaf { bounds, start, end } => {
-                let mut new_bounds = self.circles[*start].aabb();
-                for i in *start + 1..*end {
-                    new_bounds = new_bounds.union(&self.circles[i].aabb())
+            BVHNode::Leaf { refs, .. } => {
+                let mut closest: Option<(usize, f32)> = None;
+                for &i in refs {
+                    let closest_t = closest.map_or(t_max, |(_, t)| t);
+                    if let Some(t) = self.objects[i].hit(ray, t_min, closest_t) {
+                        closest = Some((i, t));
+                    }
                 }
-                *bounds = new_bounds;
+                closest
             }
-        };
-        self.nodes[node_idx] = node;
-    }
-
-    fn subdivide(&mut self, node_idx: usize, threshold: usize) {
-        let node = self.nodes[node_idx];
-        let node = match node {
-            BVHNode::Internal {
-                bounds,
-                left,
-                right,
-            } => {
-                self.subdivide(left, threshold);
-                self.subdivide(right, threshold);
-                return;
-            }
-            BVHNode::Leaf { bounds, start, end } => {
-                // Don't subdivide if the number of circles within threshold:
-                if end - start <= threshold {
-                    return;
-                }
+        }
+    }
 
-                // Compute the longest axis, on which we will split
-                let extent = bounds.ub - bounds.lb;
-                let mut axis = 0;
-                if extent.y > extent.x {
-                    axis = 1
-                };
-                if extent.z > extent[axis] {
-                    axis = 2
-                };
-
-                // Get the median circle
-                let split = bounds.lb[axis] + extent[axis] / 2.0;
-                let (mut i, mut j) = (start, end - 1);
-                while i <= j {
-                    if self.circles[i].translation[axis] < split {
-                        i += 1;
-                    } else {
-                        self.circles.swap(i, j);
-                        j -= 1;
-                    }
-                }
+    // Collapses the binary tree into a 4-wide tree: every internal node is
+    // merged with its grandchildren so each wide node holds up to four child
+    // bounds, reducing the number of node visits during traversal.
+    fn into_wide4(self) -> Bvh4 {
+        let mut wide_nodes = Vec::new();
 
-                if i == end - 1 || i == start {
-                    // Either empty or one sided, so make no changes.
-                    // This is probably unreachable given i use the median
-                    // and a threshold, but here to be safe.
-                    return;
-                }
+        let root = match &self.nodes[self.root] {
+            BVHNode::Internal { .. } => Self::build_wide4_node(&self.nodes, self.root, &mut wide_nodes),
+            BVHNode::Leaf { refs, .. } => {
+                let mut wide = Bvh4Node::empty();
+                wide.set_slot(
+                    0,
+                    self.nodes[self.root].bounds(),
+                    Bvh4Child::Leaf { refs: refs.clone() },
+                );
+                wide.child_count = 1;
+                wide_nodes.push(wide);
+                0
+            }
+        };
 
-                let left = BVHNode::Leaf {
-                    bounds: Default::default(),
-                    start: start,
-                    end: i,
-                };
-                let right = BVHNode::Leaf {
-                    bounds: Default::default(),
-                    start: i,
-                    end: end,
-                };
-
-                let l = self.nodes.len();
-                self.nodes.push(left);
-                let r = self.nodes.len();
-                self.nodes.push(right);
-
-                self.compute_bounds(l);
-                self.compute_bounds(r);
-                self.subdivide(l, threshold);
-                self.subdivide(r, threshold);
+        Bvh4 {
+            nodes: wide_nodes,
+            root,
+            circles: self.objects,
+        }
+    }
 
+    // Takes a binary `Internal` node's two children and, for each one that is
+    // itself `Internal`, replaces it with its own two children (the
+    // grandchildren), yielding 2-4 candidates that become this wide node's
+    // children.
+    fn build_wide4_node(nodes: &[BVHNode], node_idx: usize, wide_nodes: &mut Vec<Bvh4Node>) -> usize {
+        let BVHNode::Internal { left, right, .. } = &nodes[node_idx] else {
+            unreachable!("build_wide4_node called on a leaf node")
+        };
+        let (left, right) = (*left, *right);
+
+        let mut candidates = Vec::with_capacity(4);
+        for child in [left, right] {
+            match &nodes[child] {
                 BVHNode::Internal {
-                    bounds: Default::default(),
-                    left: l,
-                    right: r,
+                    left: gl, right: gr, ..
+                } => {
+                    candidates.push(*gl);
+                    candidates.push(*gr);
                 }
+                BVHNode::Leaf { .. } => candidates.push(child),
             }
-        };
+        }
 
-        self.nodes[node_idx] = node;
-        self.compute_bounds(node_idx);
+        let mut wide = Bvh4Node::empty();
+        wide.child_count = candidates.len();
+        for (slot, &candidate) in candidates.iter().enumerate() {
+            let bounds = nodes[candidate].bounds();
+            let child = match &nodes[candidate] {
+                BVHNode::Internal { .. } => {
+                    Bvh4Child::Node(Self::build_wide4_node(nodes, candidate, wide_nodes))
+                }
+                BVHNode::Leaf { refs, .. } => Bvh4Child::Leaf { refs: refs.clone() },
+            };
+            wide.set_slot(slot, bounds, child);
+        }
+
+        let this_idx = wide_nodes.len();
+        wide_nodes.push(wide);
+        this_idx
     }
+}
 
-    fn new(circles: Vec<Circle>) -> BVH {
-        let mut bvh = BVH {
-            nodes: vec![BVHNode::Leaf {
-                bounds: AABB::default(),
-                start: 0,
-                end: circles.len(),
-            }],
-            circles,
-        };
+// A 4-wide BVH node: child bounds are stored as one array per axis (rather
+// than four interleaved AABBs) so all four children can be slab-tested in a
+// single pass before descending into any of them.
+#[derive(Clone, Debug)]
+struct Bvh4Node {
+    lb_x: [f32; 4],
+    lb_y: [f32; 4],
+    lb_z: [f32; 4],
+    ub_x: [f32; 4],
+    ub_y: [f32; 4],
+    ub_z: [f32; 4],
+    children: [Bvh4Child; 4],
+    child_count: usize,
+}
+
+#[derive(Clone, Debug)]
+enum Bvh4Child {
+    Node(usize),
+    Leaf { refs: Vec<usize> },
+    Empty,
+}
+
+impl Bvh4Node {
+    // An empty AABB never passes the slab test, so unused slots are safe to
+    // test unconditionally alongside real children.
+    fn empty() -> Bvh4Node {
+        Bvh4Node {
+            lb_x: [f32::INFINITY; 4],
+            lb_y: [f32::INFINITY; 4],
+            lb_z: [f32::INFINITY; 4],
+            ub_x: [f32::NEG_INFINITY; 4],
+            ub_y: [f32::NEG_INFINITY; 4],
+            ub_z: [f32::NEG_INFINITY; 4],
+            children: [
+                Bvh4Child::Empty,
+                Bvh4Child::Empty,
+                Bvh4Child::Empty,
+                Bvh4Child::Empty,
+            ],
+            child_count: 0,
+        }
+    }
 
-        bvh.compute_bounds(0);
+    fn set_slot(&mut self, slot: usize, bounds: AABB, child: Bvh4Child) {
+        self.lb_x[slot] = bounds.lb.x;
+        self.lb_y[slot] = bounds.lb.y;
+        self.lb_z[slot] = bounds.lb.z;
+        self.ub_x[slot] = bounds.ub.x;
+        self.ub_y[slot] = bounds.ub.y;
+        self.ub_z[slot] = bounds.ub.z;
+        self.children[slot] = child;
+    }
 
-        // Subdivide until all BVHs have at most 4 elements
-        bvh.subdivide(0, 2);
+    fn slot_bounds(&self, slot: usize) -> AABB {
+        AABB {
+            lb: Vec3::new(self.lb_x[slot], self.lb_y[slot], self.lb_z[slot]),
+            ub: Vec3::new(self.ub_x[slot], self.ub_y[slot], self.ub_z[slot]),
+        }
+    }
 
-        bvh
+    fn draw(&self, draw: &Draw) {
+        for slot in 0..self.child_count {
+            self.slot_bounds(slot).draw(draw);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bvh4 {
+    nodes: Vec<Bvh4Node>,
+    root: usize,
+    circles: Vec<Circle>,
+}
+
+impl Bvh4 {
+    fn draw(&self, draw: &Draw) {
+        for node in &self.nodes {
+            node.draw(draw);
+        }
+        for circle in &self.circles {
+            circle.draw(draw);
+        }
+    }
+
+    fn cast_ray(&self, ray: &Ray) -> Option<(usize, f32)> {
+        self.cast_ray_node(self.root, ray, 0.0001, f32::INFINITY)
+    }
+
+    fn cast_ray_node(&self, node_idx: usize, ray: &Ray, t_min: f32, t_max: f32) -> Option<(usize, f32)> {
+        let node = &self.nodes[node_idx];
+
+        // Test all four child slabs up front, then only descend into the
+        // slots that were actually hit.
+        let hits: [bool; 4] = std::array::from_fn(|slot| node.slot_bounds(slot).hit(ray, t_min, t_max));
+
+        let mut closest: Option<(usize, f32)> = None;
+        for (slot, &hit) in hits.iter().enumerate().take(node.child_count) {
+            if !hit {
+                continue;
+            }
+            let slot_t_max = closest.map_or(t_max, |(_, t)| t);
+            let hit = match &node.children[slot] {
+                Bvh4Child::Node(idx) => self.cast_ray_node(*idx, ray, t_min, slot_t_max),
+                Bvh4Child::Leaf { refs } => {
+                    let mut leaf_closest: Option<(usize, f32)> = None;
+                    for &i in refs {
+                        let closest_t = leaf_closest.map_or(slot_t_max, |(_, t)| t);
+                        if let Some(t) = self.circles[i].hit(ray, t_min, closest_t) {
+                            leaf_closest = Some((i, t));
+                        }
+                    }
+                    leaf_closest
+                }
+                Bvh4Child::Empty => None,
+            };
+            if hit.is_some() {
+                closest = hit;
+            }
+        }
+        closest
     }
 }
 
 #[derive(Debug)]
 struct Model {
     _window: window::Id,
-    bvh: BVH,
+    bvh: BVH<Circle>,
     circles: Vec<Circle>,
 }
 
@@ -246,11 +1115,11 @@ fn model(app: &App) -> Model {
         })
         .collect_vec();
 
-    let bvh = BVH::new(circles);
+    let bvh = BVH::new(circles, BuildStrategy::Sah);
 
     Model {
         _window,
-        circles: bvh.circles.clone(),
+        circles: bvh.objects.clone(),
         bvh,
     }
 }
@@ -258,7 +1127,7 @@ fn model(app: &App) -> Model {
 fn update(_app: &App, _model: &mut Model, _update: Update) {}
 
 fn view(app: &App, model: &Model, frame: Frame) {
-    let win = app.window_rect();
+    let _win = app.window_rect();
     let draw = app.draw();
 
     draw.background().color(BLACK);
@@ -271,3 +1140,101 @@ fn view(app: &App, model: &Model, frame: Frame) {
 
     draw.to_frame(app, &frame).unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circle(x: f32, radius: f32) -> Circle {
+        Circle {
+            translation: Vec3::new(x, 0.0, 0.0),
+            radius,
+        }
+    }
+
+    #[test]
+    fn cast_ray_returns_closest_hit() {
+        let circles = vec![circle(10.0, 1.0), circle(20.0, 1.0), circle(30.0, 1.0)];
+        let bvh = BVH::new(circles, BuildStrategy::Sah);
+        let ray = Ray {
+            origin: Vec3::ZERO,
+            dir: Vec3::X,
+        };
+
+        let (idx, t) = bvh.cast_ray(&ray).expect("ray should hit a circle");
+        assert_eq!(bvh.objects[idx].translation.x, 10.0);
+        assert!((t - 9.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn sbvh_terminates_on_straddling_primitives() {
+        // Primitives that all share a left wall (`lb.x == 0`) with varying
+        // widths: any spatial split on x inside the node sends every reference
+        // to the left child (each either lies left of the plane or straddles
+        // it), leaving it unchanged, which used to make `build_subtree` recurse
+        // forever on an identical (refs, bounds) pair.
+        let circles: Vec<Circle> = (0..64)
+            .map(|i| {
+                let width = 1.0 + i as f32;
+                circle(width / 2.0, width / 2.0)
+            })
+            .collect();
+        let bvh = BVH::new(circles, BuildStrategy::Sbvh);
+        assert!(
+            bvh.nodes.len() < 10_000,
+            "tree grew unboundedly: {} nodes",
+            bvh.nodes.len()
+        );
+    }
+
+    #[test]
+    fn query_overlaps_has_no_self_or_duplicate_pairs() {
+        // Large, mutually-overlapping circles built with the Sbvh strategy:
+        // whatever splits the build takes, no pair may pair a primitive with
+        // itself or repeat an unordered pair.
+        let circles = vec![
+            circle(0.0, 50.0),
+            circle(5.0, 50.0),
+            circle(10.0, 50.0),
+            circle(100.0, 1.0),
+        ];
+        let bvh = BVH::new(circles, BuildStrategy::Sbvh);
+        let pairs = bvh.query_overlaps();
+
+        let mut seen = std::collections::HashSet::new();
+        for &(i, j) in &pairs {
+            assert_ne!(i, j, "overlap query should never report a self-pair");
+            let key = if i <= j { (i, j) } else { (j, i) };
+            assert!(seen.insert(key), "duplicate unordered pair {:?}", key);
+        }
+    }
+
+    #[test]
+    fn query_aabb_collects_each_touching_primitive_once() {
+        // Large, mutually-overlapping circles built with the Sbvh strategy:
+        // a region covering the three large ones must report each of them
+        // exactly once, and not the distant small one.
+        let circles = vec![
+            circle(0.0, 50.0),
+            circle(5.0, 50.0),
+            circle(10.0, 50.0),
+            circle(100.0, 1.0),
+        ];
+        let bvh = BVH::new(circles, BuildStrategy::Sbvh);
+
+        let region = AABB {
+            lb: Vec3::new(-60.0, -60.0, -60.0),
+            ub: Vec3::new(60.0, 60.0, 60.0),
+        };
+        let mut hits = bvh.query_aabb(region);
+        hits.sort_unstable();
+        assert_eq!(hits, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn new_does_not_panic_on_empty_objects() {
+        let bvh = BVH::new(Vec::<Circle>::new(), BuildStrategy::Sah);
+        assert!(bvh.query_overlaps().is_empty());
+        assert!(bvh.query_aabb(AABB::empty()).is_empty());
+    }
+}